@@ -0,0 +1,198 @@
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// First line of every bundle. Bundles may be concatenated, so the line
+/// can also appear again between entries.
+pub const MAGIC: &str = "RCAT-BUNDLE 1";
+
+/// Entry header prefix: `FILE <byte length> <path>`, then exactly that many
+/// bytes of content and a newline
+const FILE_TAG: &str = "FILE ";
+
+pub fn write_header(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "{}", MAGIC)
+}
+
+/// Append one file. `path` is stored with `/` separators and must not
+/// contain a newline, since headers are line based.
+pub fn write_entry(out: &mut dyn Write, path: &Path, contents: &[u8]) -> io::Result<()> {
+    let name = portable_path(path)?;
+    writeln!(out, "{}{} {}", FILE_TAG, contents.len(), name)?;
+    out.write_all(contents)?;
+    out.write_all(b"\n")
+}
+
+fn portable_path(path: &Path) -> io::Result<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => {
+                parts.push(part.to_str().ok_or_else(|| {
+                    invalid(format!("path is not valid UTF-8: {}", path.display()))
+                })?)
+            }
+            Component::CurDir => {}
+            _ => return Err(invalid(format!("not a relative path: {}", path.display()))),
+        }
+    }
+    let name = parts.join("/");
+    if name.is_empty() || name.contains('\n') {
+        return Err(invalid(format!("cannot bundle path: {:?}", path)));
+    }
+    Ok(name)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// One restored file: a path that is safe to join onto the target
+/// directory, and its contents
+pub struct Entry {
+    pub path: PathBuf,
+    pub contents: Vec<u8>,
+}
+
+/// Reads the entries of a bundle written by [`write_header`] and
+/// [`write_entry`]. Format problems are `InvalidData` errors.
+pub struct Reader<R> {
+    input: R,
+    seen_header: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(input: R) -> Self {
+        Reader {
+            input,
+            seen_header: false,
+        }
+    }
+
+    pub fn next_entry(&mut self) -> io::Result<Option<Entry>> {
+        loop {
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return if self.seen_header {
+                    Ok(None)
+                } else {
+                    Err(invalid("not an rcat bundle (empty input)".to_string()))
+                };
+            }
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            if line == MAGIC {
+                self.seen_header = true;
+                continue;
+            }
+            if !self.seen_header {
+                return Err(invalid("not an rcat bundle (missing header)".to_string()));
+            }
+            let header = line
+                .strip_prefix(FILE_TAG)
+                .ok_or_else(|| invalid(format!("unexpected line: {:?}", line)))?;
+            let (len, name) = header
+                .split_once(' ')
+                .ok_or_else(|| invalid(format!("malformed entry header: {:?}", line)))?;
+            let len: u64 = len
+                .parse()
+                .map_err(|_| invalid(format!("bad length in entry header: {:?}", line)))?;
+            let path = safe_path(name)
+                .ok_or_else(|| invalid(format!("refusing unsafe path: {:?}", name)))?;
+            let mut contents = Vec::new();
+            (&mut self.input).take(len).read_to_end(&mut contents)?;
+            let mut newline = [0; 1];
+            if contents.len() as u64 != len || self.input.read(&mut newline)? != 1 {
+                return Err(invalid(format!("truncated entry: {}", name)));
+            }
+            if newline[0] != b'\n' {
+                return Err(invalid(format!("entry not terminated: {}", name)));
+            }
+            return Ok(Some(Entry { path, contents }));
+        }
+    }
+}
+
+/// `name` as a relative path that stays inside the directory it is joined
+/// onto: no absolute paths, drive prefixes, `..` or empty names
+fn safe_path(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for part in name.split('/') {
+        let mut components = Path::new(part).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(part)), None) => path.push(part),
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(bundle: &[u8]) -> io::Result<Vec<Entry>> {
+        let mut reader = Reader::new(bundle);
+        let mut entries = Vec::new();
+        while let Some(entry) = reader.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    fn error_of(bundle: &[u8]) -> String {
+        let err = read_all(bundle).err().expect("bundle should be rejected");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        err.to_string()
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        let mut bundle = Vec::new();
+        write_header(&mut bundle).unwrap();
+        write_entry(&mut bundle, Path::new("./a/b.txt"), b"one\ntwo").unwrap();
+        write_entry(&mut bundle, Path::new("c"), b"").unwrap();
+        let entries = read_all(&bundle).unwrap();
+        let paths: Vec<&Path> = entries.iter().map(|e| e.path.as_path()).collect();
+        assert_eq!(paths, [Path::new("a/b.txt"), Path::new("c")]);
+        assert_eq!(entries[0].contents, b"one\ntwo");
+    }
+
+    #[test]
+    fn unsafe_paths_are_refused() {
+        for name in ["../x", "a/../../x", "/etc/passwd", "a//b", "a/./b", ""] {
+            assert_eq!(safe_path(name), None, "{:?}", name);
+        }
+        assert_eq!(safe_path("a/b"), Some(PathBuf::from("a/b")));
+        let bundle = format!("{}\nFILE 1 ../x\nx\n", MAGIC);
+        assert!(error_of(bundle.as_bytes()).contains("unsafe path"));
+        let bundle = format!("{}\nFILE 1 /tmp/x\nx\n", MAGIC);
+        assert!(error_of(bundle.as_bytes()).contains("unsafe path"));
+    }
+
+    #[test]
+    fn absolute_paths_are_not_written() {
+        let mut bundle = Vec::new();
+        assert!(write_entry(&mut bundle, Path::new("/tmp/x"), b"x").is_err());
+        assert!(write_entry(&mut bundle, Path::new("../x"), b"x").is_err());
+        assert!(bundle.is_empty());
+    }
+
+    #[test]
+    fn truncated_entries_are_errors() {
+        let bundle = format!("{}\nFILE 10 a\nshort\n", MAGIC);
+        assert!(error_of(bundle.as_bytes()).contains("truncated entry"));
+        let bundle = format!("{}\nFILE 5 a\nshort", MAGIC);
+        assert!(error_of(bundle.as_bytes()).contains("truncated entry"));
+        let bundle = format!("{}\nFILE 2 a\nshort\n", MAGIC);
+        assert!(error_of(bundle.as_bytes()).contains("not terminated"));
+    }
+
+    #[test]
+    fn bad_headers_are_errors() {
+        let bad_length = format!("{}\nFILE -1 a\nx\n", MAGIC);
+        assert!(error_of(bad_length.as_bytes()).contains("bad length"));
+        let no_name = format!("{}\nFILE 1\nx\n", MAGIC);
+        assert!(error_of(no_name.as_bytes()).contains("malformed"));
+        assert!(error_of(b"FILE 1 a\nx\n").contains("missing header"));
+        assert!(error_of(b"").contains("empty input"));
+    }
+}
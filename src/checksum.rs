@@ -0,0 +1,61 @@
+use clap::ValueEnum;
+use sha2::Digest;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Digest algorithms available to `--checksum`
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Checksum {
+    Md5,
+    Sha1,
+    Sha256,
+    Blake3,
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Checksum::Md5 => "md5",
+            Checksum::Sha1 => "sha1",
+            Checksum::Sha256 => "sha256",
+            Checksum::Blake3 => "blake3",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Lowercase hex digest of the file at `path`, streamed in chunks so large
+/// files aren't held in memory. An empty file yields the algorithm's
+/// well-known empty-input digest.
+pub fn file_digest(path: &Path, algorithm: Checksum) -> io::Result<String> {
+    let file = fs::File::open(path)?;
+    match algorithm {
+        Checksum::Md5 => digest::<md5::Md5>(file),
+        Checksum::Sha1 => digest::<sha1::Sha1>(file),
+        Checksum::Sha256 => digest::<sha2::Sha256>(file),
+        Checksum::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_reader(file)?;
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+    }
+}
+
+fn digest<D: Digest>(mut reader: impl Read) -> io::Result<String> {
+    let mut hasher = D::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
@@ -0,0 +1,302 @@
+use clap::ValueEnum;
+use std::env;
+use syntect::highlighting::{Color, Style};
+use syntect::util::as_24_bit_terminal_escaped;
+
+/// How many colors the terminal can display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorDepth {
+    /// The 16 basic ANSI colors
+    #[value(name = "16")]
+    Ansi16,
+    /// The xterm 256-color palette
+    #[value(name = "256")]
+    Ansi256,
+    /// 24-bit RGB
+    Truecolor,
+    /// Detect from COLORTERM / TERM
+    Auto,
+}
+
+/// xterm's default RGB values for the 16 basic colors
+const ANSI16_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Channel levels of the 6x6x6 cube in the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// Resolve `Auto` from the environment, the way most terminal tools do:
+    /// `COLORTERM=truecolor|24bit` means truecolor, a `TERM` mentioning
+    /// 256 colors means 256, anything else gets the basic 16.
+    pub fn resolve(self) -> Self {
+        if self != ColorDepth::Auto {
+            return self;
+        }
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        if colorterm.contains("truecolor") || colorterm.contains("24bit") {
+            return ColorDepth::Truecolor;
+        }
+        if env::var("TERM").unwrap_or_default().contains("256") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+/// Like syntect's `as_24_bit_terminal_escaped`, but downsampling foreground
+/// colors to the given depth
+pub fn terminal_escaped(regions: &[(Style, &str)], depth: ColorDepth) -> String {
+    let escape = match depth {
+        ColorDepth::Truecolor | ColorDepth::Auto => {
+            return as_24_bit_terminal_escaped(regions, false);
+        }
+        ColorDepth::Ansi256 => |c: Color| format!("\x1b[38;5;{}m", to_ansi256(c)),
+        ColorDepth::Ansi16 => |c: Color| format!("\x1b[{}m", to_ansi16(c)),
+    };
+    let mut out = String::new();
+    for (style, text) in regions {
+        out.push_str(&escape(style.foreground));
+        out.push_str(text);
+    }
+    out
+}
+
+/// Foreground escape for `c`, downsampled to `depth`
+pub fn fg_escape(c: Color, depth: ColorDepth) -> String {
+    match depth {
+        ColorDepth::Truecolor | ColorDepth::Auto => {
+            format!("\x1b[38;2;{};{};{}m", c.r, c.g, c.b)
+        }
+        ColorDepth::Ansi256 => format!("\x1b[38;5;{}m", to_ansi256(c)),
+        ColorDepth::Ansi16 => format!("\x1b[{}m", to_ansi16(c)),
+    }
+}
+
+/// Stops of the `--age-heatmap` gradient, oldest (cool) to newest (warm)
+const HEAT_STOPS: [(u8, u8, u8); 3] = [(70, 130, 255), (245, 195, 60), (255, 70, 40)];
+
+/// Color for an age `t` between 0 (oldest) and 1 (newest)
+pub fn heat_color(t: f64) -> Color {
+    let t = t.clamp(0.0, 1.0) * (HEAT_STOPS.len() - 1) as f64;
+    let i = (t as usize).min(HEAT_STOPS.len() - 2);
+    let (from, to, f) = (HEAT_STOPS[i], HEAT_STOPS[i + 1], t - i as f64);
+    let mix = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * f).round() as u8;
+    Color {
+        r: mix(from.0, to.0),
+        g: mix(from.1, to.1),
+        b: mix(from.2, to.2),
+        a: 0xff,
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (i32::from(x) - i32::from(y)).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// Nearest entry of the 256-color palette, choosing between the color cube
+/// and the grayscale ramp
+fn to_ansi256(c: Color) -> u8 {
+    let level = |v: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &l)| (i32::from(l) - i32::from(v)).abs())
+            .map(|(i, _)| i as u8)
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(c.r), level(c.g), level(c.b));
+    let cube = (
+        CUBE_LEVELS[r as usize],
+        CUBE_LEVELS[g as usize],
+        CUBE_LEVELS[b as usize],
+    );
+    let cube_index = 16 + 36 * r + 6 * g + b;
+
+    let avg = ((u16::from(c.r) + u16::from(c.g) + u16::from(c.b)) / 3) as u8;
+    let gray_step = (avg.saturating_sub(8) / 10).min(23);
+    let gray_level = 8 + 10 * gray_step;
+    let gray_index = 232 + gray_step;
+
+    let rgb = (c.r, c.g, c.b);
+    if distance(rgb, (gray_level, gray_level, gray_level)) < distance(rgb, cube) {
+        gray_index
+    } else {
+        cube_index
+    }
+}
+
+/// SGR foreground code of the nearest basic ANSI color
+fn to_ansi16(c: Color) -> u8 {
+    let index = ANSI16_PALETTE
+        .iter()
+        .enumerate()
+        .min_by_key(|&(_, &p)| distance((c.r, c.g, c.b), p))
+        .map(|(i, _)| i as u8)
+        .unwrap_or(7);
+    if index < 8 {
+        30 + index
+    } else {
+        90 + index - 8
+    }
+}
+
+/// Truncate `s` to at most `max` visible characters, appending `…` when
+/// anything was cut. ANSI escape sequences (CSI and OSC) don't count towards
+/// the width and are never split; if the line is colored, a reset is emitted before the
+/// marker so the color of the cut-off text doesn't leak.
+pub fn truncate_visible(s: &str, max: usize) -> String {
+    let mut out = String::with_capacity(s.len().min(max * 4));
+    let mut visible = 0;
+    let mut colored = false;
+    let mut linked = false;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            colored = true;
+            out.push(c);
+            if chars.peek() == Some(&'[') {
+                // CSI: parameters up to and including the final byte
+                for c in chars.by_ref() {
+                    out.push(c);
+                    if ('@'..='~').contains(&c) && c != '[' {
+                        break;
+                    }
+                }
+            } else if chars.peek() == Some(&']') {
+                // OSC (e.g. --hyperlinks): up to the ESC \ terminator
+                linked = true;
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        out.extend(chars.next());
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        if visible == max {
+            if colored {
+                out.push_str("\x1b[0m");
+            }
+            if linked {
+                // close a hyperlink the cut may have left open
+                out.push_str("\x1b]8;;\x1b\\");
+            }
+            out.push('…');
+            return out;
+        }
+        out.push(c);
+        visible += 1;
+    }
+    out
+}
+
+/// Wrap visible characters of `s` in OSC 8 hyperlinks. Each link is a
+/// `(start, end, url)` range of visible character indices, in order and
+/// non-overlapping; ANSI escapes in `s` don't count as characters.
+pub fn hyperlink_visible(s: &str, links: &[(usize, usize, &str)]) -> String {
+    let mut out = String::with_capacity(s.len() + links.len() * 32);
+    let mut links = links.iter().peekable();
+    let mut open: Option<usize> = None;
+    let mut visible = 0;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            out.push(c);
+            if chars.peek() == Some(&'[') {
+                for c in chars.by_ref() {
+                    out.push(c);
+                    if ('@'..='~').contains(&c) && c != '[' {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        if open == Some(visible) {
+            out.push_str("\x1b]8;;\x1b\\");
+            open = None;
+        }
+        if let Some(&&(start, end, url)) = links.peek()
+            && start == visible
+        {
+            out.push_str(&format!("\x1b]8;;{}\x1b\\", url));
+            open = Some(end);
+            links.next();
+        }
+        out.push(c);
+        visible += 1;
+    }
+    if open.is_some() {
+        out.push_str("\x1b]8;;\x1b\\");
+    }
+    out
+}
+
+/// Show the visible character ranges `(start, end)` of `s` in reverse
+/// video, like a pager's search highlight. Ranges are in order and
+/// non-overlapping; escapes inside a range re-apply it, so highlighting
+/// colors (or a reset) there don't cut it short.
+pub fn emphasize_visible(s: &str, ranges: &[(usize, usize)]) -> String {
+    const ON: &str = "\x1b[7m";
+    const OFF: &str = "\x1b[27m";
+    let mut out = String::with_capacity(s.len() + ranges.len() * 9);
+    let mut ranges = ranges.iter().peekable();
+    let mut open: Option<usize> = None;
+    let mut visible = 0;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if open == Some(visible) {
+            out.push_str(OFF);
+            open = None;
+        }
+        if c == '\x1b' {
+            out.push(c);
+            if chars.peek() == Some(&'[') {
+                for c in chars.by_ref() {
+                    out.push(c);
+                    if ('@'..='~').contains(&c) && c != '[' {
+                        break;
+                    }
+                }
+            }
+            if open.is_some() {
+                out.push_str(ON);
+            }
+            continue;
+        }
+        if let Some(&&(start, end)) = ranges.peek()
+            && start == visible
+        {
+            out.push_str(ON);
+            open = Some(end);
+            ranges.next();
+        }
+        out.push(c);
+        visible += 1;
+    }
+    if open.is_some() {
+        out.push_str(OFF);
+    }
+    out
+}
@@ -0,0 +1,61 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Compression formats rcat reads through transparently
+#[derive(Debug, Clone, Copy)]
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+}
+
+impl Compression {
+    /// The format implied by `path`'s extension, if any
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+        match ext.to_ascii_lowercase().as_str() {
+            "gz" => Some(Compression::Gzip),
+            "bz2" => Some(Compression::Bzip2),
+            "xz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    /// Stream the decompressed content of `file`
+    pub fn reader(self, file: fs::File) -> Box<dyn Read> {
+        match self {
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(file)),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(file)),
+            Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(file)),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Compression::Gzip => "gzip",
+            Compression::Bzip2 => "bzip2",
+            Compression::Xz => "xz",
+        };
+        f.write_str(name)
+    }
+}
+
+/// `path` without its compression extension (`app.log.gz` → `app.log`),
+/// which is what syntax detection should look at
+pub fn inner_path(path: &Path) -> PathBuf {
+    path.with_extension("")
+}
+
+/// Open `path`, decompressing it when `decompress` is set and its extension
+/// names a supported format
+pub fn open(path: &Path, decompress: bool) -> io::Result<Box<dyn Read>> {
+    let file = fs::File::open(path)?;
+    match Compression::from_path(path).filter(|_| decompress) {
+        Some(compression) => Ok(compression.reader(file)),
+        None => Ok(Box::new(file)),
+    }
+}
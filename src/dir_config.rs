@@ -0,0 +1,44 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Name of the per-directory settings file
+pub const CONFIG_FILE: &str = ".rcat.toml";
+
+/// Settings a `.rcat.toml` overrides for its directory and everything
+/// below it. Unset keys are inherited from the parent directory, and at
+/// the top from the command line.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DirConfig {
+    /// List the files instead of dumping them, like `--list`
+    pub list: Option<bool>,
+    /// Dump without highlighting, like `--no-color`
+    pub no_color: Option<bool>,
+    /// Extensions to skip, on top of `--exclude-ext`
+    pub exclude_ext: Option<Vec<String>>,
+}
+
+impl DirConfig {
+    /// The `.rcat.toml` of `dir`, `None` if it has none
+    pub fn load(dir: &Path) -> Option<Result<Self, String>> {
+        let file = dir.join(CONFIG_FILE);
+        if !file.is_file() {
+            return None;
+        }
+        let parsed = fs::read_to_string(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|text| toml::from_str(&text).map_err(|e| e.to_string()));
+        Some(parsed)
+    }
+
+    /// `child` layered on top of `self`: every key `child` sets wins,
+    /// lists included (they replace rather than extend)
+    pub fn merged(&self, child: DirConfig) -> DirConfig {
+        DirConfig {
+            list: child.list.or(self.list),
+            no_color: child.no_color.or(self.no_color),
+            exclude_ext: child.exclude_ext.or_else(|| self.exclude_ext.clone()),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use regex::Regex;
+use std::path::Path;
+use syntect::highlighting::{Highlighter, Style, Theme};
+use syntect::parsing::Scope;
+
+/// Keywords of the common languages, highlighted wherever they appear
+const KEYWORDS: &str = "\
+    and as async await break case catch class const continue def default defer del do elif \
+    else enum except export extends false finally fn for from func function go if impl \
+    implements import in interface is lambda let loop match mod mut new nil None not null or \
+    package pass private protected pub public raise return self Self static struct super \
+    switch this throw trait true True False try type use var void where while with yield";
+
+/// Extensions whose line comments start with `#` rather than `//`
+const HASH_COMMENTS: &[&str] = &[
+    "bash", "cfg", "cmake", "conf", "ini", "mk", "pl", "py", "r", "rb", "sh", "toml", "yaml",
+    "yml", "zsh",
+];
+
+/// Extensions whose line comments start with `--`
+const DASH_COMMENTS: &[&str] = &["hs", "lua", "sql"];
+
+/// `--fast-highlight`: one regex pass per line that only tells comments,
+/// strings and keywords apart. Nothing carries over between lines, so a
+/// block comment or string spanning lines is only colored on its first.
+pub struct FastHighlighter {
+    pattern: Regex,
+    plain: Style,
+    comment: Style,
+    string: Style,
+    keyword: Style,
+}
+
+impl FastHighlighter {
+    /// Styles come from `theme`, comment syntax from the extension of `path`
+    pub fn new(theme: &Theme, path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let line_comment = if HASH_COMMENTS.contains(&ext.as_str()) {
+            "#"
+        } else if DASH_COMMENTS.contains(&ext.as_str()) {
+            "--"
+        } else {
+            "//"
+        };
+        // Rust's `'a` lifetimes would pair up like strings, so only char
+        // literals are quoted with `'` there
+        let single_quoted = if ext == "rs" {
+            r"'(?:\\.|[^'\\])'"
+        } else {
+            r"'(?:\\.|[^'\\])*'"
+        };
+        let pattern = format!(
+            r#"(?P<comment>{}.*|/\*.*?(?:\*/|$))|(?P<string>"(?:\\.|[^"\\])*"?|{})|\b(?P<keyword>{})\b"#,
+            regex::escape(line_comment),
+            single_quoted,
+            KEYWORDS.split_whitespace().collect::<Vec<_>>().join("|")
+        );
+        let highlighter = Highlighter::new(theme);
+        let style = |scope: &str| {
+            highlighter.style_for_stack(&[Scope::new(scope).expect("valid scope name")])
+        };
+        FastHighlighter {
+            pattern: Regex::new(&pattern).expect("valid fast highlighting pattern"),
+            plain: highlighter.get_default(),
+            comment: style("comment"),
+            string: style("string"),
+            keyword: style("keyword"),
+        }
+    }
+
+    /// Styled regions of `line`, in the shape `HighlightLines` produces
+    pub fn highlight_line<'a>(&self, line: &'a str) -> Vec<(Style, &'a str)> {
+        let mut regions = Vec::new();
+        let mut last = 0;
+        for caps in self.pattern.captures_iter(line) {
+            let whole = caps.get(0).expect("group 0 always matches");
+            if whole.start() > last {
+                regions.push((self.plain, &line[last..whole.start()]));
+            }
+            let style = if caps.name("comment").is_some() {
+                self.comment
+            } else if caps.name("string").is_some() {
+                self.string
+            } else {
+                self.keyword
+            };
+            regions.push((style, whole.as_str()));
+            last = whole.end();
+        }
+        if last < line.len() {
+            regions.push((self.plain, &line[last..]));
+        }
+        regions
+    }
+}